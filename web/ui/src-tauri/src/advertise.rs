@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use tauri::State;
+
+use crate::discovery::SERVICE_TYPE;
+
+struct Advertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+/// Holds the running advertisement, if any, so `stop_advertising` can
+/// unregister it and shut the daemon down cleanly.
+#[derive(Default)]
+pub struct AdvertiseState {
+    advertisement: Mutex<Option<Advertisement>>,
+}
+
+#[tauri::command]
+pub async fn start_advertising(
+    name: String,
+    port: u16,
+    path: String,
+    version: String,
+    state: State<'_, AdvertiseState>,
+) -> Result<(), String> {
+    if state.advertisement.lock().unwrap().is_some() {
+        return Ok(());
+    }
+
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mdns init: {e}"))?;
+    let hostname = format!("{name}.local.");
+    let properties = [("path", path.as_str()), ("version", version.as_str())];
+
+    let service = ServiceInfo::new(SERVICE_TYPE, &name, &hostname, "", port, &properties[..])
+        .map_err(|e| format!("service info: {e}"))?
+        .enable_addr_auto();
+    let fullname = service.get_fullname().to_string();
+
+    daemon
+        .register(service)
+        .map_err(|e| format!("mdns register: {e}"))?;
+
+    *state.advertisement.lock().unwrap() = Some(Advertisement { daemon, fullname });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_advertising(state: State<'_, AdvertiseState>) -> Result<(), String> {
+    if let Some(advertisement) = state.advertisement.lock().unwrap().take() {
+        if let Ok(receiver) = advertisement.daemon.unregister(&advertisement.fullname) {
+            let _ = tokio::task::spawn_blocking(move || receiver.recv()).await;
+        }
+        let _ = advertisement.daemon.shutdown();
+    }
+    Ok(())
+}
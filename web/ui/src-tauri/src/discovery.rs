@@ -0,0 +1,405 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use rustls::pki_types::ServerName;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::tls::{HostnameOverrides, IpOrHost};
+
+pub(crate) const SERVICE_TYPE: &str = "_orb._tcp.local.";
+
+const WELL_KNOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Lazily-built client with a short timeout, so a firewalled/unresponsive
+/// host's `.well-known` lookup can't stall this process indefinitely.
+fn well_known_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(WELL_KNOWN_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiscoveredServer {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub url: String,
+    pub version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct WellKnownOrbServer {
+    host: String,
+    port: Option<u16>,
+}
+
+/// A server whose certificate name differs from the host/port a connection
+/// actually needs to dial, discovered either via the advertised
+/// `delegated_host` TXT property or a `.well-known/orb/server` lookup.
+struct Delegation {
+    target_host: String,
+    target_port: u16,
+    cert_name: String,
+}
+
+async fn resolve_delegation(
+    advertised_host: &str,
+    advertised_port: u16,
+    delegated_host_prop: Option<&str>,
+) -> Option<Delegation> {
+    if let Some(cert_name) = delegated_host_prop {
+        return Some(Delegation {
+            target_host: advertised_host.to_string(),
+            target_port: advertised_port,
+            cert_name: cert_name.to_string(),
+        });
+    }
+
+    let well_known_url =
+        format!("http://{advertised_host}:{advertised_port}/.well-known/orb/server");
+    let body: WellKnownOrbServer = well_known_client()
+        .get(&well_known_url)
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+
+    Some(Delegation {
+        target_host: body.host,
+        target_port: body.port.unwrap_or(advertised_port),
+        cert_name: advertised_host.to_string(),
+    })
+}
+
+async fn build_server(info: &ServiceInfo, overrides: &Arc<HostnameOverrides>) -> DiscoveredServer {
+    let host = info
+        .get_addresses()
+        .iter()
+        .next()
+        .map(|a| a.to_string())
+        .unwrap_or_else(|| info.get_hostname().trim_end_matches('.').to_string());
+    let port = info.get_port();
+
+    let props = info.get_properties();
+    let path = props
+        .get("path")
+        .map(|v| v.val_str().to_string())
+        .unwrap_or_else(|| "/".to_string());
+    let version = props
+        .get("version")
+        .map(|v| v.val_str().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let delegated_host = props.get("delegated_host").map(|v| v.val_str().to_string());
+
+    let delegation = resolve_delegation(&host, port, delegated_host.as_deref()).await;
+
+    let (target_host, target_port, scheme) = match &delegation {
+        Some(d) => {
+            if let Ok(cert_name) = ServerName::try_from(d.cert_name.clone()) {
+                overrides.set(IpOrHost::parse(&d.target_host), cert_name);
+            }
+            (d.target_host.as_str(), d.target_port, "https")
+        }
+        None => (host.as_str(), port, "http"),
+    };
+
+    let url = format!("{scheme}://{target_host}:{target_port}{path}");
+    let name = info
+        .get_fullname()
+        .split('.')
+        .next()
+        .unwrap_or("Orb Server")
+        .to_string();
+
+    DiscoveredServer {
+        name,
+        host,
+        port,
+        url,
+        version,
+    }
+}
+
+struct DiscoveryTask {
+    daemon: ServiceDaemon,
+    handle: tokio::task::JoinHandle<()>,
+}
+
+/// Shared state for the long-lived discovery stream, keyed by mDNS fullname
+/// so `ServiceResolved`/`ServiceRemoved` events can be reconciled against it.
+#[derive(Default)]
+pub struct DiscoveryState {
+    servers: Arc<Mutex<HashMap<String, DiscoveredServer>>>,
+    task: Mutex<Option<DiscoveryTask>>,
+    tls_overrides: Arc<HostnameOverrides>,
+    /// Per-`ServiceResolved` delegation-resolution tasks that are still
+    /// running. Tracked so `stop_discovery` can abort them instead of
+    /// letting one finish after the fact and resurrect an entry in a map
+    /// the frontend was just told is empty.
+    inflight_resolutions: Mutex<Vec<tokio::task::JoinHandle<()>>>,
+}
+
+impl DiscoveryState {
+    /// Current known servers, for subsystems (e.g. monitoring) that need a
+    /// point-in-time view without subscribing to the event stream.
+    pub fn snapshot(&self) -> Vec<DiscoveredServer> {
+        self.servers.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Connection-target -> certificate-name overrides populated as
+    /// delegated servers are discovered, for use by the HTTPS client.
+    pub fn tls_overrides(&self) -> Arc<HostnameOverrides> {
+        self.tls_overrides.clone()
+    }
+
+    /// Inserts (or refreshes) an mDNS-resolved server, keyed by its mDNS
+    /// fullname. If a server with the same URL is already known under a
+    /// different key (e.g. reported earlier by the relay), that entry is
+    /// dropped first so the two sources stay deduplicated by URL, matching
+    /// `merge_external`'s contract in the other direction.
+    fn upsert_resolved<R: tauri::Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        fullname: String,
+        server: DiscoveredServer,
+    ) {
+        let mut guard = self.servers.lock().unwrap();
+
+        if let Some(dup_key) = guard
+            .iter()
+            .find(|(key, s)| **key != fullname && s.url == server.url)
+            .map(|(key, _)| key.clone())
+        {
+            guard.remove(&dup_key);
+        }
+
+        let existed = guard.insert(fullname, server.clone()).is_some();
+        drop(guard);
+
+        let event = if existed { "server-updated" } else { "server-found" };
+        let _ = app.emit(event, &server);
+    }
+
+    /// Registers a delegation-resolution task so `stop_discovery` can abort
+    /// it if discovery is stopped before it finishes.
+    fn track_inflight(&self, handle: tokio::task::JoinHandle<()>) {
+        let mut inflight = self.inflight_resolutions.lock().unwrap();
+        inflight.retain(|h| !h.is_finished());
+        inflight.push(handle);
+    }
+
+    fn abort_inflight(&self) {
+        for handle in self.inflight_resolutions.lock().unwrap().drain(..) {
+            handle.abort();
+        }
+    }
+
+    /// Merges a batch of servers from a non-mDNS source (e.g. the relay)
+    /// into the shared map, keyed so they don't collide with mDNS entries,
+    /// and reconciles against whatever that source previously reported.
+    /// Servers already known by URL from a *different* source are skipped to
+    /// keep the list deduplicated by URL; a server this same source already
+    /// reported is still refreshed, so a peer that stays up across polls
+    /// keeps emitting `server-updated` instead of going stale.
+    pub fn merge_external<R: tauri::Runtime>(
+        &self,
+        app: &AppHandle<R>,
+        source: &str,
+        found: Vec<DiscoveredServer>,
+    ) {
+        let mut guard = self.servers.lock().unwrap();
+        let prefix = format!("{source}:");
+        let known_urls: std::collections::HashSet<String> = guard
+            .iter()
+            .filter(|(key, _)| !key.starts_with(&prefix))
+            .map(|(_, s)| s.url.clone())
+            .collect();
+
+        let seen_keys: std::collections::HashSet<String> = found
+            .iter()
+            .map(|s| format!("{prefix}{}", s.url))
+            .collect();
+        let stale: Vec<String> = guard
+            .keys()
+            .filter(|k| k.starts_with(&prefix) && !seen_keys.contains(*k))
+            .cloned()
+            .collect();
+        for key in stale {
+            if let Some(server) = guard.remove(&key) {
+                let _ = app.emit("server-lost", &server);
+            }
+        }
+
+        for server in found {
+            if known_urls.contains(&server.url) {
+                continue;
+            }
+            let key = format!("{prefix}{}", server.url);
+            let existed = guard.insert(key, server.clone()).is_some();
+            let event = if existed { "server-updated" } else { "server-found" };
+            let _ = app.emit(event, &server);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "relay";
+
+    fn sample_server(url: &str) -> DiscoveredServer {
+        DiscoveredServer {
+            name: "Test Server".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            url: url.to_string(),
+            version: "1.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn merge_external_skips_peer_already_known_under_another_source() {
+        let app = tauri::test::mock_app();
+        let handle = app.handle();
+        let discovery = DiscoveryState::default();
+
+        discovery
+            .servers
+            .lock()
+            .unwrap()
+            .insert("mdns-fullname".to_string(), sample_server("http://127.0.0.1:8080/"));
+
+        discovery.merge_external(handle, SOURCE, vec![sample_server("http://127.0.0.1:8080/")]);
+
+        let guard = discovery.servers.lock().unwrap();
+        assert_eq!(guard.len(), 1);
+        assert!(guard.contains_key("mdns-fullname"));
+    }
+
+    #[test]
+    fn merge_external_refreshes_a_peer_its_own_source_already_reported() {
+        let app = tauri::test::mock_app();
+        let handle = app.handle();
+        let discovery = DiscoveryState::default();
+
+        discovery.merge_external(handle, SOURCE, vec![sample_server("http://10.0.0.2:9000/")]);
+        let mut refreshed = sample_server("http://10.0.0.2:9000/");
+        refreshed.version = "2.0".to_string();
+        discovery.merge_external(handle, SOURCE, vec![refreshed]);
+
+        let guard = discovery.servers.lock().unwrap();
+        assert_eq!(guard.len(), 1);
+        assert_eq!(guard[&format!("{SOURCE}:http://10.0.0.2:9000/")].version, "2.0");
+    }
+
+    #[test]
+    fn merge_external_drops_entries_its_source_no_longer_reports() {
+        let app = tauri::test::mock_app();
+        let handle = app.handle();
+        let discovery = DiscoveryState::default();
+
+        discovery.merge_external(handle, SOURCE, vec![sample_server("http://10.0.0.3:9000/")]);
+        assert_eq!(discovery.servers.lock().unwrap().len(), 1);
+
+        discovery.merge_external(handle, SOURCE, vec![]);
+        assert_eq!(discovery.servers.lock().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn upsert_resolved_dedupes_against_an_existing_relay_entry_by_url() {
+        let app = tauri::test::mock_app();
+        let handle = app.handle();
+        let discovery = DiscoveryState::default();
+
+        discovery.servers.lock().unwrap().insert(
+            format!("{SOURCE}:http://10.0.0.4:9000/"),
+            sample_server("http://10.0.0.4:9000/"),
+        );
+
+        discovery.upsert_resolved(
+            handle,
+            "mdns-fullname".to_string(),
+            sample_server("http://10.0.0.4:9000/"),
+        );
+
+        let guard = discovery.servers.lock().unwrap();
+        assert_eq!(guard.len(), 1);
+        assert!(guard.contains_key("mdns-fullname"));
+    }
+}
+
+#[tauri::command]
+pub async fn start_discovery(
+    app: AppHandle,
+    state: State<'_, Arc<DiscoveryState>>,
+) -> Result<(), String> {
+    if state.task.lock().unwrap().is_some() {
+        return Ok(());
+    }
+
+    let mdns = ServiceDaemon::new().map_err(|e| format!("mdns init: {e}"))?;
+    let receiver = mdns
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("mdns browse: {e}"))?;
+
+    let discovery_state = state.inner().clone();
+    let app_handle = app.clone();
+    let servers = state.servers.clone();
+
+    let handle = tokio::spawn(async move {
+        loop {
+            let rx = receiver.clone();
+            match tokio::task::spawn_blocking(move || rx.recv()).await {
+                Ok(Ok(ServiceEvent::ServiceResolved(info))) => {
+                    // Resolving a delegation can make a network call (the
+                    // `.well-known` lookup); spawn it off so one slow/silent
+                    // host doesn't stall discovery of every other server.
+                    // The handle is tracked so `stop_discovery` can abort it
+                    // if it's still running when discovery is stopped.
+                    let task_discovery_state = discovery_state.clone();
+                    let app_handle = app_handle.clone();
+                    let resolve_handle = tokio::spawn(async move {
+                        let server = build_server(&info, &task_discovery_state.tls_overrides).await;
+                        let fullname = info.get_fullname().to_string();
+                        task_discovery_state.upsert_resolved(&app_handle, fullname, server);
+                    });
+                    discovery_state.track_inflight(resolve_handle);
+                }
+                Ok(Ok(ServiceEvent::ServiceRemoved(_ty, fullname))) => {
+                    let removed = servers.lock().unwrap().remove(&fullname);
+                    if let Some(server) = removed {
+                        let _ = app_handle.emit("server-lost", &server);
+                    }
+                }
+                Ok(Ok(_)) => continue,
+                Ok(Err(_)) | Err(_) => break,
+            }
+        }
+    });
+
+    *state.task.lock().unwrap() = Some(DiscoveryTask { daemon: mdns, handle });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_discovery(state: State<'_, Arc<DiscoveryState>>) -> Result<(), String> {
+    let task = state.task.lock().unwrap().take();
+    if let Some(task) = task {
+        task.handle.abort();
+        let _ = task.daemon.stop_browse(SERVICE_TYPE);
+        let _ = task.daemon.shutdown();
+    }
+    state.abort_inflight();
+    state.servers.lock().unwrap().clear();
+    Ok(())
+}
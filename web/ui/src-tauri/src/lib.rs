@@ -1,88 +1,93 @@
-use mdns_sd::{ServiceDaemon, ServiceEvent};
-use serde::Serialize;
-use std::time::Duration;
+mod advertise;
+mod discovery;
+mod monitor;
+mod proxy;
+mod relay;
+mod tls;
 
-#[derive(Debug, Clone, Serialize)]
-pub struct DiscoveredServer {
-    pub name: String,
-    pub host: String,
-    pub port: u16,
-    pub url: String,
-    pub version: String,
-}
-
-#[tauri::command]
-async fn discover_servers() -> Result<Vec<DiscoveredServer>, String> {
-    let mdns = ServiceDaemon::new().map_err(|e| format!("mdns init: {e}"))?;
-    let receiver = mdns
-        .browse("_orb._tcp.local.")
-        .map_err(|e| format!("mdns browse: {e}"))?;
+use std::sync::Arc;
 
-    let mut servers: Vec<DiscoveredServer> = Vec::new();
-    let deadline = tokio::time::Instant::now() + Duration::from_secs(3);
+use advertise::AdvertiseState;
+use discovery::DiscoveryState;
+use monitor::MonitorState;
+use proxy::ProxyTarget;
+use relay::RelayState;
+use tauri::Manager;
+use tower::Service;
 
-    loop {
-        let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
-        if remaining.is_zero() {
-            break;
-        }
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let discovery_state = Arc::new(DiscoveryState::default());
+    let monitor_state = MonitorState::default();
+    let http_client = tls::build_client(discovery_state.tls_overrides())
+        .expect("failed to build TLS-aware HTTP client");
 
-        let rx = receiver.clone();
-        let wait = remaining.min(Duration::from_millis(500));
-        match tokio::task::spawn_blocking(move || rx.recv_timeout(wait)).await {
-            Ok(Ok(ServiceEvent::ServiceResolved(info))) => {
-                let host = info
-                    .get_addresses()
-                    .iter()
-                    .next()
-                    .map(|a| a.to_string())
-                    .unwrap_or_else(|| {
-                        info.get_hostname().trim_end_matches('.').to_string()
-                    });
-                let port = info.get_port();
+    monitor::spawn_monitor(
+        discovery_state.clone(),
+        monitor_state.statuses_handle(),
+        http_client.clone(),
+        monitor_state.interval_handle(),
+    );
 
-                let props = info.get_properties();
-                let path = props
-                    .get("path")
-                    .map(|v| v.val_str().to_string())
-                    .unwrap_or_else(|| "/".to_string());
-                let version = props
-                    .get("version")
-                    .map(|v| v.val_str().to_string())
-                    .unwrap_or_else(|| "unknown".to_string());
+    let proxy_target = ProxyTarget::default();
+    let proxy_router = proxy::router(proxy_target.clone(), http_client);
 
-                let url = format!("http://{}:{}{}", host, port, path);
-                let name = info
-                    .get_fullname()
-                    .split('.')
-                    .next()
-                    .unwrap_or("Orb Server")
-                    .to_string();
+    tauri::Builder::default()
+        .manage(discovery_state)
+        .manage(monitor_state)
+        .manage(AdvertiseState::default())
+        .manage(RelayState::default())
+        .manage(proxy_target)
+        .register_asynchronous_uri_scheme_protocol("orb", move |_ctx, request, responder| {
+            let mut router = proxy_router.clone();
+            tauri::async_runtime::spawn(async move {
+                let (parts, body) = request.into_parts();
+                let axum_request = axum::http::Request::from_parts(parts, axum::body::Body::from(body));
 
-                if !servers.iter().any(|s| s.url == url) {
-                    servers.push(DiscoveredServer {
-                        name,
-                        host,
-                        port,
-                        url,
-                        version,
-                    });
+                match router.call(axum_request).await {
+                    Ok(response) => {
+                        let (parts, body) = response.into_parts();
+                        let reader = proxy::body_to_sync_reader(body);
+                        responder.respond(tauri::http::Response::from_parts(parts, reader));
+                    }
+                    Err(_) => {
+                        responder.respond(
+                            tauri::http::Response::builder()
+                                .status(500)
+                                .body(Vec::new())
+                                .unwrap(),
+                        );
+                    }
                 }
+            });
+        })
+        .invoke_handler(tauri::generate_handler![
+            discovery::start_discovery,
+            discovery::stop_discovery,
+            monitor::get_server_statuses,
+            monitor::set_probe_interval,
+            advertise::start_advertising,
+            advertise::stop_advertising,
+            relay::start_relay_discovery,
+            relay::stop_relay_discovery,
+            proxy::select_proxy_target,
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Unregister mDNS/relay presence and stop the browse tasks
+            // before the process actually exits, rather than abandoning
+            // them when the window closes. The `exit(0)` below raises
+            // ExitRequested again with a code set, which is let through.
+            if let tauri::RunEvent::ExitRequested { api, code: None, .. } = event {
+                api.prevent_exit();
+                let app_handle = app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let _ = advertise::stop_advertising(app_handle.state()).await;
+                    let _ = discovery::stop_discovery(app_handle.state()).await;
+                    let _ = relay::stop_relay_discovery(app_handle.state()).await;
+                    app_handle.exit(0);
+                });
             }
-            _ => continue,
-        }
-    }
-
-    let _ = mdns.stop_browse("_orb._tcp.local.");
-    let _ = mdns.shutdown();
-
-    Ok(servers)
-}
-
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![discover_servers])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        });
 }
@@ -0,0 +1,108 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::State;
+use tokio::sync::RwLock;
+
+use crate::discovery::DiscoveryState;
+
+const DEFAULT_PROBE_INTERVAL_SECS: u64 = 15;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerStatus {
+    pub url: String,
+    pub http_status: u16,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    pub last_update: u64,
+}
+
+pub struct MonitorState {
+    statuses: Arc<RwLock<Vec<ServerStatus>>>,
+    interval_secs: Arc<AtomicU64>,
+}
+
+impl Default for MonitorState {
+    fn default() -> Self {
+        Self {
+            statuses: Arc::new(RwLock::new(Vec::new())),
+            interval_secs: Arc::new(AtomicU64::new(DEFAULT_PROBE_INTERVAL_SECS)),
+        }
+    }
+}
+
+fn now_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+async fn probe(client: &reqwest::Client, url: &str) -> ServerStatus {
+    let started = tokio::time::Instant::now();
+
+    let (http_status, error) = match client.get(url).timeout(PROBE_TIMEOUT).send().await {
+        Ok(resp) => (resp.status().as_u16(), None),
+        Err(e) => (0, Some(e.to_string())),
+    };
+
+    ServerStatus {
+        url: url.to_string(),
+        http_status,
+        latency_ms: started.elapsed().as_millis() as u64,
+        error,
+        last_update: now_epoch(),
+    }
+}
+
+/// Spawns the background task that periodically probes every discovered
+/// server and refreshes `MonitorState`. Intended to be called once from
+/// `run()`; the task lives for the lifetime of the app. `client` must be
+/// built with [`crate::tls::build_client`] so delegated HTTPS servers
+/// verify correctly instead of failing the hostname check. The interval is
+/// re-read every cycle so `set_probe_interval` takes effect without a
+/// restart.
+pub fn spawn_monitor(
+    discovery: Arc<DiscoveryState>,
+    statuses: Arc<RwLock<Vec<ServerStatus>>>,
+    client: reqwest::Client,
+    interval_secs: Arc<AtomicU64>,
+) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let servers = discovery.snapshot();
+            let results =
+                futures_util::future::join_all(servers.iter().map(|s| probe(&client, &s.url)))
+                    .await;
+
+            *statuses.write().await = results;
+
+            let interval = Duration::from_secs(interval_secs.load(Ordering::Relaxed).max(1));
+            tokio::time::sleep(interval).await;
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_server_statuses(state: State<'_, MonitorState>) -> Result<Vec<ServerStatus>, String> {
+    Ok(state.statuses.read().await.clone())
+}
+
+#[tauri::command]
+pub async fn set_probe_interval(seconds: u64, state: State<'_, MonitorState>) -> Result<(), String> {
+    state.interval_secs.store(seconds.max(1), Ordering::Relaxed);
+    Ok(())
+}
+
+impl MonitorState {
+    pub fn statuses_handle(&self) -> Arc<RwLock<Vec<ServerStatus>>> {
+        self.statuses.clone()
+    }
+
+    pub fn interval_handle(&self) -> Arc<AtomicU64> {
+        self.interval_secs.clone()
+    }
+}
@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Request, State as AxumState};
+use axum::http::StatusCode;
+use axum::response::Response;
+use axum::routing::any;
+use axum::Router;
+use tokio::sync::RwLock;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+
+use crate::discovery::DiscoveredServer;
+
+const FORWARDED_REQUEST_HEADERS: &[&str] = &["range", "accept", "accept-encoding", "user-agent"];
+const FORWARDED_RESPONSE_HEADERS: &[&str] = &[
+    "content-type",
+    "content-length",
+    "content-range",
+    "accept-ranges",
+    "cache-control",
+    "etag",
+    "last-modified",
+];
+
+/// The server the webview's `orb://` requests are currently forwarded to.
+/// Set by the frontend when the user picks a server from the discovery list.
+#[derive(Clone, Default)]
+pub struct ProxyTarget(Arc<RwLock<Option<DiscoveredServer>>>);
+
+impl ProxyTarget {
+    pub async fn set(&self, server: Option<DiscoveredServer>) {
+        *self.0.write().await = server;
+    }
+
+    async fn current(&self) -> Option<DiscoveredServer> {
+        self.0.read().await.clone()
+    }
+}
+
+#[tauri::command]
+pub async fn select_proxy_target(
+    server: DiscoveredServer,
+    target: tauri::State<'_, ProxyTarget>,
+) -> Result<(), String> {
+    target.set(Some(server)).await;
+    Ok(())
+}
+
+#[derive(Clone)]
+struct ProxyState {
+    target: ProxyTarget,
+    client: reqwest::Client,
+}
+
+/// Builds the `tower::Service` that backs the `orb://` protocol handler. Every
+/// request is forwarded to the currently selected [`DiscoveredServer`],
+/// passing `Range` through so large media seeks instead of buffering whole.
+/// `client` must be built with [`crate::tls::build_client`] so delegated
+/// HTTPS servers verify correctly instead of failing the hostname check.
+pub fn router(target: ProxyTarget, client: reqwest::Client) -> Router {
+    Router::new()
+        .fallback(any(forward))
+        .with_state(ProxyState { target, client })
+}
+
+async fn forward(AxumState(state): AxumState<ProxyState>, req: Request) -> Response {
+    let Some(server) = state.target.current().await else {
+        return Response::builder()
+            .status(StatusCode::SERVICE_UNAVAILABLE)
+            .body(Body::from("no orb server selected"))
+            .unwrap();
+    };
+
+    let path_and_query = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let upstream_url = format!("{}{}", server.url.trim_end_matches('/'), path_and_query);
+
+    let mut upstream_req = state.client.request(req.method().clone(), &upstream_url);
+    for header in FORWARDED_REQUEST_HEADERS {
+        if let Some(value) = req.headers().get(*header) {
+            upstream_req = upstream_req.header(*header, value.clone());
+        }
+    }
+
+    let upstream_resp = match upstream_req.send().await {
+        Ok(resp) => resp,
+        Err(e) => {
+            return Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .body(Body::from(format!("orb proxy: {e}")))
+                .unwrap();
+        }
+    };
+
+    let status = upstream_resp.status();
+    let mut builder = Response::builder().status(status.as_u16());
+    for header in FORWARDED_RESPONSE_HEADERS {
+        if let Some(value) = upstream_resp.headers().get(*header) {
+            builder = builder.header(*header, value.clone());
+        }
+    }
+
+    builder
+        .body(Body::from_stream(upstream_resp.bytes_stream()))
+        .unwrap_or_else(|_| {
+            Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(Body::empty())
+                .unwrap()
+        })
+}
+
+/// Bridges an axum streaming response body into the blocking `Read` Tauri's
+/// asynchronous URI scheme protocol expects, so the body is read in chunks
+/// off the wire rather than collected into memory up front.
+pub fn body_to_sync_reader(body: Body) -> SyncIoBridge<StreamReader<axum::body::BodyDataStream, axum::body::Bytes>> {
+    use futures_util::TryStreamExt;
+
+    let stream = body.into_data_stream().map_err(std::io::Error::other);
+    SyncIoBridge::new(StreamReader::new(stream))
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::discovery::{DiscoveredServer, DiscoveryState};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+const SOURCE: &str = "relay";
+
+#[derive(Serialize)]
+struct RegisterRequest<'a> {
+    node_id: &'a str,
+    group: &'a str,
+    token: Option<&'a str>,
+    #[serde(flatten)]
+    server: &'a DiscoveredServer,
+}
+
+#[derive(Serialize)]
+struct DeregisterRequest<'a> {
+    node_id: &'a str,
+    group: &'a str,
+    token: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct PeersResponse {
+    #[serde(default)]
+    peers: Vec<DiscoveredServer>,
+}
+
+struct RelayTask {
+    handle: tokio::task::JoinHandle<()>,
+    client: reqwest::Client,
+    endpoint: String,
+    node_id: String,
+    group: String,
+    token: Option<String>,
+}
+
+/// Handle to the background task polling the rendezvous/relay endpoint for
+/// peers outside the local subnet, alongside the mDNS browse in
+/// [`crate::discovery`].
+#[derive(Default)]
+pub struct RelayState {
+    task: std::sync::Mutex<Option<RelayTask>>,
+}
+
+#[tauri::command]
+pub async fn start_relay_discovery(
+    endpoint: String,
+    group: String,
+    token: Option<String>,
+    node_id: String,
+    this_node: DiscoveredServer,
+    app: AppHandle,
+    discovery: State<'_, Arc<DiscoveryState>>,
+    state: State<'_, RelayState>,
+) -> Result<(), String> {
+    if state.task.lock().unwrap().is_some() {
+        return Ok(());
+    }
+
+    let client = reqwest::Client::new();
+    let _ = client
+        .post(format!("{endpoint}/register"))
+        .json(&RegisterRequest {
+            node_id: &node_id,
+            group: &group,
+            token: token.as_deref(),
+            server: &this_node,
+        })
+        .send()
+        .await;
+
+    let discovery_state = discovery.inner().clone();
+    let app_handle = app.clone();
+    let task_client = client.clone();
+    let task_endpoint = endpoint.clone();
+    let task_node_id = node_id.clone();
+    let task_group = group.clone();
+    let task_token = token.clone();
+
+    let handle = tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            // Re-register every poll cycle so a rendezvous server that
+            // expires stale registrations doesn't make this node silently
+            // vanish from peers' view while it's still up.
+            let _ = task_client
+                .post(format!("{task_endpoint}/register"))
+                .json(&RegisterRequest {
+                    node_id: &task_node_id,
+                    group: &task_group,
+                    token: task_token.as_deref(),
+                    server: &this_node,
+                })
+                .send()
+                .await;
+
+            let response = task_client
+                .get(format!("{task_endpoint}/peers"))
+                .query(&[("group", task_group.as_str())])
+                .send()
+                .await;
+
+            if let Ok(resp) = response {
+                if let Ok(peers) = resp.json::<PeersResponse>().await {
+                    discovery_state.merge_external(&app_handle, SOURCE, peers.peers);
+                }
+            }
+        }
+    });
+
+    *state.task.lock().unwrap() = Some(RelayTask {
+        handle,
+        client,
+        endpoint,
+        node_id,
+        group,
+        token,
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_relay_discovery(state: State<'_, RelayState>) -> Result<(), String> {
+    if let Some(task) = state.task.lock().unwrap().take() {
+        task.handle.abort();
+        let _ = task
+            .client
+            .post(format!("{}/deregister", task.endpoint))
+            .json(&DeregisterRequest {
+                node_id: &task.node_id,
+                group: &task.group,
+                token: task.token.as_deref(),
+            })
+            .send()
+            .await;
+    }
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{DigitallySignedStruct, Error as TlsError, RootCertStore, SignatureScheme};
+
+/// Key for the override map: the thing a connection is actually dialed
+/// against (an mDNS-advertised IP, or a hostname resolved via
+/// `.well-known/orb/server`), as opposed to the name its certificate is
+/// issued for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum IpOrHost {
+    Ip(IpAddr),
+    Host(String),
+}
+
+impl IpOrHost {
+    pub fn parse(s: &str) -> Self {
+        s.parse::<IpAddr>()
+            .map(IpOrHost::Ip)
+            .unwrap_or_else(|_| IpOrHost::Host(s.to_string()))
+    }
+}
+
+/// Connection target -> certificate name overrides, populated by delegation
+/// resolution in [`crate::discovery`].
+#[derive(Default)]
+pub struct HostnameOverrides {
+    overrides: RwLock<HashMap<IpOrHost, ServerName<'static>>>,
+}
+
+impl HostnameOverrides {
+    pub fn set(&self, target: IpOrHost, cert_name: ServerName<'static>) {
+        self.overrides.write().unwrap().insert(target, cert_name);
+    }
+
+    fn lookup(&self, target: &IpOrHost) -> Option<ServerName<'static>> {
+        self.overrides.read().unwrap().get(target).cloned()
+    }
+}
+
+/// Wraps the default WebPKI verifier so a connection made by IP/delegated
+/// host can still verify against the hostname its certificate was actually
+/// issued for. Verification is tried first against the overridden name and,
+/// if that fails, falls back to the name the connection presented.
+pub struct DelegatingCertVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    overrides: Arc<HostnameOverrides>,
+}
+
+impl DelegatingCertVerifier {
+    pub fn new(
+        roots: RootCertStore,
+        overrides: Arc<HostnameOverrides>,
+    ) -> Result<Arc<Self>, TlsError> {
+        let inner = WebPkiServerVerifier::builder(Arc::new(roots))
+            .build()
+            .map_err(|e| TlsError::General(e.to_string()))?;
+        Ok(Arc::new(Self { inner, overrides }))
+    }
+
+    fn key_for(server_name: &ServerName<'_>) -> IpOrHost {
+        match server_name {
+            ServerName::IpAddress(ip) => IpOrHost::Ip((*ip).into()),
+            ServerName::DnsName(name) => IpOrHost::Host(name.as_ref().to_string()),
+            other => IpOrHost::Host(other.to_str().into_owned()),
+        }
+    }
+}
+
+impl std::fmt::Debug for DelegatingCertVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DelegatingCertVerifier").finish_non_exhaustive()
+    }
+}
+
+/// Builds the shared `reqwest::Client` every consumer that dials a
+/// discovered Orb server should use, so delegated HTTPS servers (connected
+/// to by IP, certified under a different name) verify against `overrides`
+/// instead of failing the default WebPKI hostname check.
+pub fn build_client(overrides: Arc<HostnameOverrides>) -> Result<reqwest::Client, String> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+    let verifier = DelegatingCertVerifier::new(roots, overrides).map_err(|e| e.to_string())?;
+    let tls_config = rustls::ClientConfig::builder()
+        .dangerous()
+        .with_custom_certificate_verifier(verifier)
+        .with_no_client_auth();
+
+    reqwest::Client::builder()
+        .use_preconfigured_tls(tls_config)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+impl ServerCertVerifier for DelegatingCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TlsError> {
+        let key = Self::key_for(server_name);
+
+        if let Some(overridden) = self.overrides.lookup(&key) {
+            if let Ok(verified) =
+                self.inner
+                    .verify_server_cert(end_entity, intermediates, &overridden, ocsp_response, now)
+            {
+                return Ok(verified);
+            }
+        }
+
+        self.inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn key_for_maps_ip_addresses_to_ipor_host_ip() {
+        let name = ServerName::IpAddress(Ipv4Addr::new(10, 0, 0, 4).into());
+        assert_eq!(
+            DelegatingCertVerifier::key_for(&name),
+            IpOrHost::Ip(Ipv4Addr::new(10, 0, 0, 4).into())
+        );
+    }
+
+    #[test]
+    fn key_for_maps_dns_names_to_ipor_host_host() {
+        let name = ServerName::try_from("orb.example.com").unwrap();
+        assert_eq!(
+            DelegatingCertVerifier::key_for(&name),
+            IpOrHost::Host("orb.example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn overrides_round_trip_and_are_keyed_by_dial_target() {
+        let overrides = HostnameOverrides::default();
+        let target = IpOrHost::Ip(Ipv4Addr::new(10, 0, 0, 4).into());
+        let cert_name = ServerName::try_from("orb.example.com").unwrap();
+
+        assert!(overrides.lookup(&target).is_none());
+
+        overrides.set(target.clone(), cert_name.clone());
+        assert_eq!(overrides.lookup(&target), Some(cert_name));
+
+        let other = IpOrHost::Host("10.0.0.5".to_string());
+        assert!(overrides.lookup(&other).is_none());
+    }
+}